@@ -0,0 +1,64 @@
+use std::fmt;
+
+use crate::components::io::IoError;
+use crate::types::{PeerId, Status};
+
+/// The error type for the light client, returned by the [`Supervisor`](crate::supervisor::Supervisor)
+/// and its [`Handle`](crate::supervisor::Handle)s.
+pub type Error = Box<ErrorKind>;
+
+/// The various errors that can be raised during the operation of the light client.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ErrorKind {
+    /// No initial trusted state.
+    NoInitialTrustedState,
+    /// No trusted state in the given status.
+    NoTrustedState(Status),
+    /// No primary peer.
+    NoPrimary,
+    /// No witnesses left.
+    NoWitnesses,
+    /// No witness left to swap to primary.
+    NoWitnessLeft,
+    /// A fork was detected, with the given forked peers.
+    ForkDetected(Vec<PeerId>),
+    /// An I/O error occurred.
+    Io(IoError),
+    /// The channel to the [`Supervisor`](crate::supervisor::Supervisor) was disconnected,
+    /// typically because the supervisor task has terminated or panicked.
+    ChannelDisconnected,
+    /// Evidence for a confirmed fork could not be reported because the peer has no flow
+    /// control credit left. The report is not retried, and the supervisor's fork processing
+    /// discards this error, so it is not returned to a [`Handle`](crate::supervisor::Handle).
+    EvidenceReportThrottled(PeerId),
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoInitialTrustedState => write!(f, "no initial trusted state"),
+            Self::NoTrustedState(status) => write!(f, "no trusted state in status {:?}", status),
+            Self::NoPrimary => write!(f, "no primary"),
+            Self::NoWitnesses => write!(f, "no witnesses"),
+            Self::NoWitnessLeft => write!(f, "no witness left"),
+            Self::ForkDetected(peers) => write!(f, "fork detected from peers {:?}", peers),
+            Self::Io(e) => write!(f, "io error: {}", e),
+            Self::ChannelDisconnected => {
+                write!(f, "channel to the supervisor was disconnected")
+            }
+            Self::EvidenceReportThrottled(peer) => write!(
+                f,
+                "could not report evidence to peer {:?}: no flow control credit left",
+                peer
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ErrorKind {}
+
+impl From<ErrorKind> for Error {
+    fn from(kind: ErrorKind) -> Self {
+        Box::new(kind)
+    }
+}
@@ -0,0 +1,55 @@
+//! The [`EventHandler`] hook, notified of [`Supervisor`](crate::supervisor::Supervisor) events.
+
+use tendermint::evidence::Evidence;
+
+use crate::types::{LightBlock, PeerId};
+
+/// Context passed to an [`EventHandler`] alongside an event tied to a single peer and the
+/// [`LightBlock`] it produced or is implicated in.
+#[derive(Clone, Debug)]
+pub struct LightBlockContext {
+    /// The peer the event concerns.
+    pub peer: PeerId,
+    /// The light block associated with the event.
+    pub light_block: LightBlock,
+}
+
+/// Context passed to an [`EventHandler`] alongside an event tied to a single peer, with no
+/// associated light block.
+#[derive(Clone, Debug)]
+pub struct PeerContext {
+    /// The peer the event concerns.
+    pub peer: PeerId,
+}
+
+/// Context passed to an [`EventHandler`] when evidence of a fork has been reported to a peer.
+#[derive(Clone, Debug)]
+pub struct EvidenceContext {
+    /// The peer the evidence was reported to.
+    pub peer: PeerId,
+    /// The evidence that was reported.
+    pub evidence: Evidence,
+}
+
+/// An observer hook invoked by the [`Supervisor`](crate::supervisor::Supervisor) at the relevant
+/// points of `verify`, `process_forks` and `report_evidence`.
+///
+/// Every method has a default no-op implementation, so implementors only need to override the
+/// events they actually care about.
+pub trait EventHandler: Send {
+    /// Called once a light block has been successfully verified and trusted.
+    fn on_verified(&self, _ctx: &LightBlockContext) {}
+
+    /// Called when the primary peer has been swapped out for a witness, e.g. because primary
+    /// verification failed.
+    fn on_primary_swapped(&self, _ctx: &PeerContext) {}
+
+    /// Called when a fork has been detected between the primary and a witness.
+    fn on_fork_detected(&self, _ctx: &LightBlockContext) {}
+
+    /// Called when a witness has been deemed faulty and removed from the peer list.
+    fn on_witness_faulty(&self, _ctx: &PeerContext) {}
+
+    /// Called once evidence of a fork has been successfully reported to a peer.
+    fn on_evidence_reported(&self, _ctx: &EvidenceContext) {}
+}
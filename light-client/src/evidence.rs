@@ -3,28 +3,35 @@ use crate::{components::io::IoError, types::PeerId};
 use tendermint::{abci::transaction::Hash, evidence::Evidence};
 use tendermint_rpc as rpc;
 
-use contracts::{contract_trait, pre};
+use async_trait::async_trait;
+use contracts::pre;
 use std::collections::HashMap;
+use std::sync::Mutex;
 
 /// Interface for reporting evidence to full nodes, typically via the RPC client.
-#[contract_trait]
-pub trait EvidenceReporter: Send {
+#[async_trait]
+pub trait EvidenceReporter: Send + Sync {
     /// Report evidence to all connected full nodes.
-    fn report(&self, e: Evidence, peer: PeerId) -> Result<Hash, IoError>;
+    async fn report(&self, e: Evidence, peer: PeerId) -> Result<Hash, IoError>;
 }
 
 /// Production implementation of the EvidenceReporter component, which reports evidence to full
 /// nodes via RPC.
-#[derive(Clone, Debug)]
+///
+/// One [`rpc::Client`] is cached per peer and reused across calls, rather than being
+/// reconstructed on every single evidence report.
+#[derive(Debug)]
 pub struct ProdEvidenceReporter {
     peer_map: HashMap<PeerId, tendermint::net::Address>,
+    clients: Mutex<HashMap<PeerId, rpc::Client>>,
 }
 
-#[contract_trait]
+#[async_trait]
 impl EvidenceReporter for ProdEvidenceReporter {
     #[pre(self.peer_map.contains_key(&peer))]
-    fn report(&self, e: Evidence, peer: PeerId) -> Result<Hash, IoError> {
-        let res = block_on(self.rpc_client_for(peer).broadcast_evidence(e));
+    async fn report(&self, e: Evidence, peer: PeerId) -> Result<Hash, IoError> {
+        let client = self.rpc_client_for(peer);
+        let res = client.broadcast_evidence(e).await;
 
         match res {
             Ok(response) => Ok(response.hash),
@@ -38,22 +45,23 @@ impl ProdEvidenceReporter {
     ///
     /// A peer map which maps peer IDS to their network address must be supplied.
     pub fn new(peer_map: HashMap<PeerId, tendermint::net::Address>) -> Self {
-        Self { peer_map }
+        Self {
+            peer_map,
+            clients: Mutex::new(HashMap::new()),
+        }
     }
 
     // FIXME: Cannot enable precondition because of "autoref lifetime" issue
     // #[pre(self.peer_map.contains_key(&peer))]
     fn rpc_client_for(&self, peer: PeerId) -> rpc::Client {
-        let peer_addr = self.peer_map.get(&peer).unwrap().to_owned();
-        rpc::Client::new(peer_addr)
-    }
-}
+        let mut clients = self.clients.lock().unwrap();
 
-fn block_on<F: std::future::Future>(f: F) -> F::Output {
-    tokio::runtime::Builder::new()
-        .basic_scheduler()
-        .enable_all()
-        .build()
-        .unwrap()
-        .block_on(f)
+        clients
+            .entry(peer)
+            .or_insert_with(|| {
+                let peer_addr = self.peer_map.get(&peer).unwrap().to_owned();
+                rpc::Client::new(peer_addr)
+            })
+            .clone()
+    }
 }
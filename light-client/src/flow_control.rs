@@ -0,0 +1,201 @@
+//! Per-peer, token-bucket style flow control for outbound RPC requests.
+
+use std::collections::HashMap;
+use std::time::Instant;
+
+use crate::types::PeerId;
+
+/// Parameters governing how a peer's [`Buffer`] recharges and how much a single request costs.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct FlowParams {
+    /// Number of credits restored to a buffer per second that elapses.
+    pub recharge_per_sec: u64,
+    /// Number of credits a single outbound request costs.
+    pub cost_per_request: u64,
+}
+
+impl FlowParams {
+    /// Constructs new `FlowParams` from the given recharge rate and request cost.
+    pub fn new(recharge_per_sec: u64, cost_per_request: u64) -> Self {
+        Self {
+            recharge_per_sec,
+            cost_per_request,
+        }
+    }
+}
+
+/// A recharging credit buffer, tracking how many requests a single peer may still be sent
+/// before it needs to be skipped or deferred for this round.
+#[derive(Copy, Clone, Debug)]
+struct Buffer {
+    current: u64,
+    max: u64,
+    last_update: Instant,
+}
+
+impl Buffer {
+    /// Constructs a new, fully charged buffer with the given capacity.
+    fn new(max: u64) -> Self {
+        Self {
+            current: max,
+            max,
+            last_update: Instant::now(),
+        }
+    }
+
+    /// Recharges the buffer based on the time elapsed since the last update.
+    fn recharge(&mut self, params: &FlowParams) {
+        let now = Instant::now();
+        let elapsed_secs = now.duration_since(self.last_update).as_secs();
+
+        if elapsed_secs > 0 {
+            let recharge = params.recharge_per_sec.saturating_mul(elapsed_secs);
+            self.current = std::cmp::min(self.max, self.current.saturating_add(recharge));
+            self.last_update = now;
+        }
+    }
+
+    /// Recharges the buffer, then returns `true` if it holds enough credit for a request,
+    /// without withdrawing it.
+    fn has_credit(&mut self, params: &FlowParams) -> bool {
+        self.recharge(params);
+        self.current >= params.cost_per_request
+    }
+
+    /// Recharges the buffer, then attempts to withdraw the cost of a single request. Returns
+    /// `true` if the request may proceed.
+    fn try_consume(&mut self, params: &FlowParams) -> bool {
+        self.recharge(params);
+
+        if self.current >= params.cost_per_request {
+            self.current -= params.cost_per_request;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// Per-peer credit-based flow control, held by the [`Supervisor`](crate::supervisor::Supervisor)
+/// and consulted before issuing an outbound RPC to a peer, whether a witness verification fetch
+/// or an [`EvidenceReporter::report`](crate::evidence::EvidenceReporter::report) call.
+///
+/// Every [`PeerId`] is lazily given a fully charged [`Buffer`] the first time a request against
+/// it is attempted. Operators can tune `max` and `recharge_per_sec` per deployment to trade off
+/// responsiveness against load on full nodes.
+#[derive(Clone, Debug)]
+pub struct FlowController {
+    params: FlowParams,
+    max: u64,
+    buffers: HashMap<PeerId, Buffer>,
+}
+
+impl FlowController {
+    /// Constructs a new `FlowController` where every peer's buffer has the given `max` capacity
+    /// and recharges/costs according to `params`.
+    pub fn new(max: u64, params: FlowParams) -> Self {
+        Self {
+            params,
+            max,
+            buffers: HashMap::new(),
+        }
+    }
+
+    /// Returns `true` if `peer` currently has enough credit for a request, without withdrawing
+    /// it. The peer's buffer is created lazily, fully charged, the first time it is seen.
+    pub fn has_credit(&mut self, peer: PeerId) -> bool {
+        let params = self.params;
+        let max = self.max;
+
+        self.buffers
+            .entry(peer)
+            .or_insert_with(|| Buffer::new(max))
+            .has_credit(&params)
+    }
+
+    /// Returns `true` if a request to `peer` may proceed right now, deducting the request's cost
+    /// from its buffer. The peer's buffer is created lazily, fully charged, the first time it is
+    /// seen.
+    pub fn allow_request(&mut self, peer: PeerId) -> bool {
+        let params = self.params;
+        let max = self.max;
+
+        self.buffers
+            .entry(peer)
+            .or_insert_with(|| Buffer::new(max))
+            .try_consume(&params)
+    }
+
+    /// Removes the buffer held for the given peer, e.g. once it has been marked as faulty and
+    /// dropped from the witness set.
+    pub fn remove(&mut self, peer: &PeerId) {
+        self.buffers.remove(peer);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn peer(id: u8) -> PeerId {
+        PeerId::new([id; PeerId::LENGTH])
+    }
+
+    fn params() -> FlowParams {
+        FlowParams::new(1, 1)
+    }
+
+    #[test]
+    fn recharge_caps_at_max() {
+        let mut buffer = Buffer {
+            current: 0,
+            max: 3,
+            last_update: Instant::now() - Duration::from_secs(100),
+        };
+
+        buffer.recharge(&params());
+
+        assert_eq!(buffer.current, 3);
+    }
+
+    #[test]
+    fn allow_request_consumes_and_recharges() {
+        let mut controller = FlowController::new(2, params());
+        let peer = peer(1);
+
+        assert!(controller.allow_request(peer));
+        assert!(controller.allow_request(peer));
+        assert!(!controller.allow_request(peer));
+
+        // Give it a whole bucket's worth of time to recharge.
+        controller.buffers.get_mut(&peer).unwrap().last_update -= Duration::from_secs(2);
+
+        assert!(controller.allow_request(peer));
+    }
+
+    #[test]
+    fn has_credit_does_not_consume() {
+        let mut controller = FlowController::new(1, params());
+        let peer = peer(2);
+
+        assert!(controller.has_credit(peer));
+        assert!(controller.has_credit(peer));
+        assert!(controller.allow_request(peer));
+        assert!(!controller.has_credit(peer));
+    }
+
+    #[test]
+    fn remove_drops_the_buffer() {
+        let mut controller = FlowController::new(1, params());
+        let peer = peer(3);
+
+        controller.allow_request(peer);
+        assert!(!controller.allow_request(peer));
+
+        controller.remove(&peer);
+
+        // The peer gets a fresh, fully charged buffer after being removed.
+        assert!(controller.allow_request(peer));
+    }
+}
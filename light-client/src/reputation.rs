@@ -0,0 +1,113 @@
+//! Per-peer reputation scoring, accumulated across calls to [`ReputationTracker::apply`] and
+//! compared against a ban threshold.
+
+use std::collections::HashMap;
+
+use crate::types::PeerId;
+
+/// The outcome the supervisor maps a misbehavior (or good behavior) to, via
+/// [`ReputationTracker::apply`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Punishment {
+    /// Disconnect the peer immediately, regardless of its current score. Reserved for
+    /// misbehavior that should never be forgiven.
+    Disconnect,
+    /// Apply the given delta to the peer's score (negative for a penalty, positive for a
+    /// reward).
+    Penalize(i32),
+}
+
+/// Penalty applied to a witness that timed out answering a request.
+pub const TIMEOUT_PENALTY: i32 = -10;
+/// Penalty applied to a witness that served a conflicting or otherwise invalid header.
+pub const FAULTY_PENALTY: i32 = -100;
+/// Reward applied to a witness for a successful, non-conflicting response.
+pub const SUCCESS_REWARD: i32 = 1;
+/// Score at or below which a peer is disconnected, by default.
+pub const DEFAULT_BAN_THRESHOLD: i32 = -100;
+
+/// Tracks a reputation score per peer, disconnecting it once the score crosses `ban_threshold`.
+#[derive(Clone, Debug)]
+pub struct ReputationTracker {
+    ban_threshold: i32,
+    scores: HashMap<PeerId, i32>,
+}
+
+impl ReputationTracker {
+    /// Constructs a new tracker that disconnects a peer once its score is at or below
+    /// `ban_threshold`.
+    pub fn new(ban_threshold: i32) -> Self {
+        Self {
+            ban_threshold,
+            scores: HashMap::new(),
+        }
+    }
+
+    /// Applies `punishment` to `peer`'s score. Returns `true` if the peer should now be
+    /// disconnected, either because its score is now at or below `ban_threshold` or because
+    /// `punishment` was [`Punishment::Disconnect`].
+    pub fn apply(&mut self, peer: PeerId, punishment: Punishment) -> bool {
+        match punishment {
+            Punishment::Disconnect => true,
+            Punishment::Penalize(delta) => {
+                let score = self.scores.entry(peer).or_insert(0);
+                *score = score.saturating_add(delta);
+                *score <= self.ban_threshold
+            }
+        }
+    }
+
+    /// Returns the current score of `peer`, or `0` if it hasn't been scored yet.
+    pub fn score(&self, peer: &PeerId) -> i32 {
+        self.scores.get(peer).copied().unwrap_or(0)
+    }
+
+    /// Removes the tracked score for a peer, e.g. once it has been disconnected.
+    pub fn remove(&mut self, peer: &PeerId) {
+        self.scores.remove(peer);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn peer(id: u8) -> PeerId {
+        PeerId::new([id; PeerId::LENGTH])
+    }
+
+    #[test]
+    fn disconnects_exactly_at_the_threshold() {
+        let mut tracker = ReputationTracker::new(-10);
+        let peer = peer(1);
+
+        assert!(!tracker.apply(peer, Punishment::Penalize(-9)));
+        assert_eq!(tracker.score(&peer), -9);
+
+        assert!(tracker.apply(peer, Punishment::Penalize(-1)));
+        assert_eq!(tracker.score(&peer), -10);
+    }
+
+    #[test]
+    fn disconnect_punishment_always_disconnects() {
+        let mut tracker = ReputationTracker::new(-10);
+        let peer = peer(2);
+
+        assert!(tracker.apply(peer, Punishment::Disconnect));
+        // `Disconnect` bypasses the score entirely, it is never recorded.
+        assert_eq!(tracker.score(&peer), 0);
+    }
+
+    #[test]
+    fn remove_resets_the_score() {
+        let mut tracker = ReputationTracker::new(-10);
+        let peer = peer(3);
+
+        tracker.apply(peer, Punishment::Penalize(-5));
+        assert_eq!(tracker.score(&peer), -5);
+
+        tracker.remove(&peer);
+
+        assert_eq!(tracker.score(&peer), 0);
+    }
+}
@@ -2,13 +2,20 @@ use contracts::pre;
 use crossbeam_channel as channel;
 
 use tendermint::evidence::{ConflictingHeadersEvidence, Evidence};
+use tendermint::Hash;
 
 use crate::bail;
 use crate::errors::{Error, ErrorKind};
+use crate::event_handler::{EventHandler, EvidenceContext, LightBlockContext, PeerContext};
 use crate::evidence::EvidenceReporter;
+use crate::flow_control::{FlowController, FlowParams};
 use crate::fork_detector::{Fork, ForkDetection, ForkDetector};
 use crate::light_client::LightClient;
 use crate::peer_list::PeerList;
+use crate::reputation::{
+    Punishment, ReputationTracker, DEFAULT_BAN_THRESHOLD, FAULTY_PENALTY, SUCCESS_REWARD,
+    TIMEOUT_PENALTY,
+};
 use crate::state::State;
 use crate::types::{Height, LightBlock, PeerId, Status};
 
@@ -16,6 +23,10 @@ pub trait Handle {
     /// Get latest trusted block from the [`Supervisor`].
     fn latest_trusted(&self) -> Result<Option<LightBlock>, Error>;
 
+    /// Get the latest status of the [`Supervisor`], including the height and hash
+    /// of the latest trusted block and the set of currently connected peers.
+    fn latest_status(&self) -> Result<LatestStatus, Error>;
+
     /// Verify to the highest block.
     fn verify_to_highest(&self) -> Result<LightBlock, Error>;
 
@@ -23,7 +34,23 @@ pub trait Handle {
     fn verify_to_target(&self, height: Height) -> Result<LightBlock, Error>;
 
     /// Terminate the underlying [`Supervisor`].
-    fn terminate(&self);
+    fn terminate(&self) -> Result<(), Error>;
+}
+
+/// A snapshot of the [`Supervisor`]'s current sync status, returned by
+/// [`Handle::latest_status`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct LatestStatus {
+    /// Height of the latest trusted block of the primary, if any.
+    pub height: Option<Height>,
+    /// Hash of the latest trusted block of the primary, if any.
+    pub block_hash: Option<Hash>,
+    /// IDs of the peers the supervisor is currently connected to (primary + witnesses).
+    pub connected_nodes: Vec<PeerId>,
+    /// IDs of the witnesses currently used for fork detection.
+    pub witnesses: Vec<PeerId>,
+    /// Current reputation score of each witness, in the same order as `witnesses`.
+    pub witness_scores: Vec<(PeerId, i32)>,
 }
 
 /// Input events sent by the [`Handle`]s to the [`Supervisor`]. They carry a [`Callback`] which is
@@ -31,13 +58,15 @@ pub trait Handle {
 #[derive(Debug)]
 enum HandleInput {
     /// Terminate the supervisor process
-    Terminate(channel::Sender<()>),
+    Terminate(channel::Sender<Result<(), Error>>),
     /// Verify to the highest height, call the provided callback with result
     VerifyToHighest(channel::Sender<Result<LightBlock, Error>>),
     /// Verify to the given height, call the provided callback with result
     VerifyToTarget(Height, channel::Sender<Result<LightBlock, Error>>),
     /// Get the latest trusted block.
     LatestTrusted(channel::Sender<Result<Option<LightBlock>, Error>>),
+    /// Get the latest status of the supervisor.
+    LatestStatus(channel::Sender<Result<LatestStatus, Error>>),
 }
 
 /// An light client `Instance` packages a `LightClient` together with its `State`.
@@ -110,6 +139,20 @@ pub struct Supervisor {
     fork_detector: Box<dyn ForkDetector>,
     /// Reporter of fork evidence
     evidence_reporter: Box<dyn EvidenceReporter>,
+    /// Per-peer flow control, bounding the rate of outbound RPCs to any single peer
+    flow_controller: FlowController,
+    /// Per-peer reputation scores, used to graduate witness punishment instead of disconnecting
+    /// on the first misbehavior
+    reputation: ReputationTracker,
+    /// Handlers notified of verification, fork detection and evidence reporting events
+    event_handlers: Vec<Box<dyn EventHandler>>,
+    /// Tokio runtime handle used to drive async I/O (e.g. evidence reporting) without building
+    /// a fresh runtime on every call
+    runtime: tokio::runtime::Handle,
+    /// Runtime built by [`Supervisor::new`] and kept alive to back `runtime` for the
+    /// lifetime of the supervisor, unless [`Supervisor::run_async`] swaps in a caller-provided
+    /// one instead
+    default_runtime: Option<tokio::runtime::Runtime>,
     /// Channel through which to reply to `Handle`s
     sender: channel::Sender<HandleInput>,
     /// Channel through which to receive events from the `Handle`s
@@ -127,21 +170,66 @@ impl std::fmt::Debug for Supervisor {
 // Ensure the `Supervisor` can be sent across thread boundaries.
 static_assertions::assert_impl_all!(Supervisor: Send);
 
+/// Default flow control parameters applied to every peer's credit buffer: 5 requests worth of
+/// burst capacity, recharging at 1 request per second.
+const DEFAULT_FLOW_MAX: u64 = 5;
+const DEFAULT_FLOW_PARAMS: FlowParams = FlowParams {
+    recharge_per_sec: 1,
+    cost_per_request: 1,
+};
+
 impl Supervisor {
     /// Constructs a new supevisor from the given list of peers and fork detector instance.
     pub fn new(
         peers: PeerList,
         fork_detector: impl ForkDetector + 'static,
         evidence_reporter: impl EvidenceReporter + 'static,
+    ) -> Self {
+        Self::with_flow_params(peers, fork_detector, evidence_reporter, DEFAULT_FLOW_PARAMS)
+    }
+
+    /// Constructs a new supervisor, as per [`Supervisor::new`], but with the per-peer flow
+    /// control buffers governed by the given `flow_params` instead of the defaults.
+    pub fn with_flow_params(
+        peers: PeerList,
+        fork_detector: impl ForkDetector + 'static,
+        evidence_reporter: impl EvidenceReporter + 'static,
+        flow_params: FlowParams,
     ) -> Self {
         let (sender, receiver) = channel::unbounded::<HandleInput>();
 
+        let default_runtime = tokio::runtime::Builder::new()
+            .basic_scheduler()
+            .enable_all()
+            .build()
+            .expect("failed to build the supervisor's default Tokio runtime");
+        let runtime = default_runtime.handle().clone();
+
         Self {
             peers,
             sender,
             receiver,
             fork_detector: Box::new(fork_detector),
             evidence_reporter: Box::new(evidence_reporter),
+            flow_controller: FlowController::new(DEFAULT_FLOW_MAX, flow_params),
+            reputation: ReputationTracker::new(DEFAULT_BAN_THRESHOLD),
+            event_handlers: Vec::new(),
+            runtime,
+            default_runtime: Some(default_runtime),
+        }
+    }
+
+    /// Registers the given event handlers, which will be notified in order at the relevant
+    /// points of the verify loop, fork processing and evidence reporting.
+    pub fn with_event_handlers(mut self, event_handlers: Vec<Box<dyn EventHandler>>) -> Self {
+        self.event_handlers = event_handlers;
+        self
+    }
+
+    /// Dispatches the given closure to every registered [`EventHandler`], in registration order.
+    fn dispatch(&self, f: impl Fn(&dyn EventHandler)) {
+        for handler in &self.event_handlers {
+            f(handler.as_ref());
         }
     }
 
@@ -156,6 +244,33 @@ impl Supervisor {
         Ok(primary.latest_trusted())
     }
 
+    /// Get the latest status, based on the latest trusted state of the primary peer
+    /// and the current set of connected peers and witnesses.
+    #[pre(self.peers.primary().is_some())]
+    fn latest_status(&self) -> Result<LatestStatus, Error> {
+        let primary = self.peers.primary().ok_or_else(|| ErrorKind::NoPrimary)?;
+        let latest_trusted = primary.latest_trusted();
+
+        let witnesses: Vec<PeerId> = self.peers.witnesses_ids().iter().copied().collect();
+        let mut connected_nodes = witnesses.clone();
+        connected_nodes.push(self.peers.primary_id());
+
+        let witness_scores = witnesses
+            .iter()
+            .map(|peer| (*peer, self.reputation.score(peer)))
+            .collect();
+
+        Ok(LatestStatus {
+            height: latest_trusted.as_ref().map(|lb| lb.height()),
+            block_hash: latest_trusted
+                .as_ref()
+                .map(|lb| lb.signed_header.header.hash()),
+            connected_nodes,
+            witnesses,
+            witness_scores,
+        })
+    }
+
     /// Verify to the highest block.
     #[pre(self.peers.primary().is_some())]
     pub fn verify_to_highest(&mut self) -> Result<LightBlock, Error> {
@@ -211,6 +326,22 @@ impl Supervisor {
                                 primary.trust_block(&light_block);
                             }
 
+                            self.dispatch(|handler| {
+                                handler.on_verified(&LightBlockContext {
+                                    peer: self.peers.primary_id(),
+                                    light_block: light_block.clone(),
+                                })
+                            });
+
+                            // None of the witnesses consulted during fork detection disagreed
+                            // with the primary, so reward them all with a small reputation bump.
+                            let witnesses: Vec<PeerId> =
+                                self.peers.witnesses_ids().iter().copied().collect();
+                            for witness in witnesses {
+                                self.reputation
+                                    .apply(witness, Punishment::Penalize(SUCCESS_REWARD));
+                            }
+
                             // No fork detected, exiting
                             return Ok(light_block);
                         }
@@ -220,7 +351,13 @@ impl Supervisor {
                 Err(_err) => {
                     // Swap primary, and continue with new primary, if there is any witness left.
                     self.peers.swap_primary()?;
-                    // TODO: Log/record error
+
+                    self.dispatch(|handler| {
+                        handler.on_primary_swapped(&PeerContext {
+                            peer: self.peers.primary_id(),
+                        })
+                    });
+
                     continue;
                 }
             }
@@ -237,19 +374,40 @@ impl Supervisor {
                 // An actual fork was detected, report evidence and record forked peer.
                 Fork::Forked { primary, witness } => {
                     let provider = witness.provider;
-                    self.report_evidence(provider, &primary, &witness)?;
+
+                    self.dispatch(|handler| {
+                        handler.on_fork_detected(&LightBlockContext {
+                            peer: provider,
+                            light_block: witness.clone(),
+                        })
+                    });
+
+                    // Check the witness's flow-control credit for the report before punish()
+                    // below removes its buffer; punishing first would make the check below
+                    // meaningless; it'd always find a fresh, fully-charged buffer regardless of
+                    // how much credit the witness had actually spent moments earlier in
+                    // detect_forks's fan-out.
+                    //
+                    // A fork was conclusively detected here regardless of whether we manage to
+                    // notify the network of it, so a throttled or failed report must not abort
+                    // the rest of this batch, nor suppress the ForkDetected result below.
+                    // TODO(xla): Manage error case.
+                    let _ = self.report_evidence(provider, &primary, &witness);
+
+                    // A confirmed, conflicting header is conclusive: disconnect the witness
+                    // outright instead of merely penalizing its score.
+                    self.punish(provider, Punishment::Disconnect);
 
                     forked.push(provider);
                 }
-                // A witness has timed out, remove it from the peer list.
+                // A witness has timed out: a small deduction, forgivable over time.
                 Fork::Timeout(provider, _error) => {
-                    self.peers.mark_witness_as_faulty(provider);
-                    // TODO: Log/record the error
+                    self.punish(provider, Punishment::Penalize(TIMEOUT_PENALTY));
                 }
-                // A witness has been deemed faulty, remove it from the peer list.
+                // A witness served a conflicting or otherwise invalid block: a large deduction,
+                // likely to cross the ban threshold on its own.
                 Fork::Faulty(block, _error) => {
-                    self.peers.mark_witness_as_faulty(block.provider);
-                    // TODO: Log/record the error
+                    self.punish(block.provider, Punishment::Penalize(FAULTY_PENALTY));
                 }
             }
         }
@@ -257,6 +415,24 @@ impl Supervisor {
         Ok(forked)
     }
 
+    /// Applies `punishment` to `peer`'s reputation score. If the peer's score has now crossed
+    /// the ban threshold (or `punishment` was [`Punishment::Disconnect`]), it is dropped from
+    /// the peer list and its flow-control buffer and reputation score are cleared. Returns
+    /// `true` if the peer was disconnected.
+    fn punish(&mut self, peer: PeerId, punishment: Punishment) -> bool {
+        let disconnect = self.reputation.apply(peer, punishment);
+
+        if disconnect {
+            self.peers.mark_witness_as_faulty(peer);
+            self.flow_controller.remove(&peer);
+            self.reputation.remove(&peer);
+
+            self.dispatch(|handler| handler.on_witness_faulty(&PeerContext { peer }));
+        }
+
+        disconnect
+    }
+
     /// Report the given evidence of a fork.
     fn report_evidence(
         &mut self,
@@ -264,22 +440,36 @@ impl Supervisor {
         primary: &LightBlock,
         witness: &LightBlock,
     ) -> Result<(), Error> {
-        let evidence = ConflictingHeadersEvidence::new(
+        if !self.flow_controller.allow_request(provider) {
+            // There is no retry/requeue mechanism for evidence reports, so a peer with no
+            // credit left means this report is simply not delivered. Surface that instead of
+            // returning `Ok(())`, which would let the caller believe the report went through.
+            bail!(ErrorKind::EvidenceReportThrottled(provider));
+        }
+
+        let evidence = Evidence::ConflictingHeaders(Box::new(ConflictingHeadersEvidence::new(
             primary.signed_header.clone(),
             witness.signed_header.clone(),
-        );
+        )));
 
-        self.evidence_reporter
-            .report(Evidence::ConflictingHeaders(Box::new(evidence)), provider)
+        self.runtime
+            .block_on(self.evidence_reporter.report(evidence.clone(), provider))
             .map_err(ErrorKind::Io)?;
 
+        self.dispatch(|handler| {
+            handler.on_evidence_reported(&EvidenceContext {
+                peer: provider,
+                evidence: evidence.clone(),
+            })
+        });
+
         Ok(())
     }
 
     /// Perform fork detection with the given block and trusted state.
     #[pre(self.peers.primary().is_some())]
     fn detect_forks(
-        &self,
+        &mut self,
         light_block: &LightBlock,
         trusted_state: &LightBlock,
     ) -> Result<ForkDetection, Error> {
@@ -287,10 +477,62 @@ impl Supervisor {
             bail!(ErrorKind::NoWitnesses);
         }
 
+        // Only fan out the verification fetch once every witness has credit left: peeking
+        // first, rather than gating on whether any single witness has budget, ensures we never
+        // send a request to a peer that can't currently afford it.
+        let witness_ids: Vec<PeerId> = self.peers.witnesses_ids().iter().copied().collect();
+        let all_have_budget = witness_ids
+            .iter()
+            .all(|peer| self.flow_controller.has_credit(*peer));
+
+        if !all_have_budget {
+            return Ok(ForkDetection::NotDetected);
+        }
+
+        for peer in witness_ids {
+            self.flow_controller.allow_request(peer);
+        }
+
         self.fork_detector
             .detect_forks(light_block, &trusted_state, self.peers.witnesses())
     }
 
+    /// Handle a single event, replying on its callback channel. Returns `true` if the event
+    /// loop (in [`Supervisor::run`] or [`Supervisor::run_async`]) should terminate afterwards.
+    fn handle_event(&mut self, event: HandleInput) -> bool {
+        match event {
+            HandleInput::LatestTrusted(sender) => {
+                let outcome = self.latest_trusted();
+                // TODO(xla): Manage error case.
+                sender.send(outcome).unwrap();
+                false
+            }
+            HandleInput::LatestStatus(sender) => {
+                let outcome = self.latest_status();
+                // TODO(xla): Manage error case.
+                sender.send(outcome).unwrap();
+                false
+            }
+            HandleInput::Terminate(sender) => {
+                // TODO(xla): Manage error case.
+                sender.send(Ok(())).unwrap();
+                true
+            }
+            HandleInput::VerifyToTarget(height, sender) => {
+                let outcome = self.verify_to_target(height);
+                // TODO(xla): Manage error case.
+                sender.send(outcome).unwrap();
+                false
+            }
+            HandleInput::VerifyToHighest(sender) => {
+                let outcome = self.verify_to_highest();
+                // TODO(xla): Manage error case.
+                sender.send(outcome).unwrap();
+                false
+            }
+        }
+    }
+
     /// Run the supervisor event loop in the same thread.
     ///
     /// This method should typically be called within a new thread with `std::thread::spawn`.
@@ -298,30 +540,29 @@ impl Supervisor {
         loop {
             let event = self.receiver.recv().unwrap();
 
-            match event {
-                HandleInput::LatestTrusted(sender) => {
-                    let outcome = self.latest_trusted();
-                    // TODO(xla): Manage error case.
-                    sender.send(outcome).unwrap();
-                }
-                HandleInput::Terminate(sender) => {
-                    // TODO(xla): Manage error case.
-                    sender.send(()).unwrap();
-                    return;
-                }
-                HandleInput::VerifyToTarget(height, sender) => {
-                    let outcome = self.verify_to_target(height);
-                    // TODO(xla): Manage error case.
-                    sender.send(outcome).unwrap();
-                }
-                HandleInput::VerifyToHighest(sender) => {
-                    let outcome = self.verify_to_highest();
-                    // TODO(xla): Manage error case.
-                    sender.send(outcome).unwrap();
-                }
+            if self.handle_event(event) {
+                return;
             }
         }
     }
+
+    /// Run the supervisor event loop on the given Tokio `runtime`, instead of requiring a
+    /// dedicated OS thread.
+    ///
+    /// The event loop itself still blocks on its channel `recv`, so it is driven via
+    /// [`spawn_blocking`](tokio::runtime::Handle::spawn_blocking) rather than a raw
+    /// `std::thread::spawn`; I/O paths such as evidence reporting are bridged through this same
+    /// `runtime`, in place of the runtime built by [`Supervisor::new`] that [`Supervisor::run`]
+    /// uses on its own.
+    pub async fn run_async(mut self, runtime: tokio::runtime::Handle) {
+        self.default_runtime = None;
+        self.runtime = runtime.clone();
+
+        runtime
+            .spawn_blocking(move || self.run())
+            .await
+            .expect("supervisor event loop panicked");
+    }
 }
 
 /// A [`Handle`] to the [`Supervisor`] which allows to communicate with
@@ -344,22 +585,38 @@ impl SupervisorHandle {
         let (sender, receiver) = channel::bounded::<Result<LightBlock, Error>>(1);
 
         let event = make_event(sender);
-        self.sender.send(event).unwrap();
+        self.sender
+            .send(event)
+            .map_err(|_| ErrorKind::ChannelDisconnected)?;
 
-        receiver.recv().unwrap()
+        receiver
+            .recv()
+            .map_err(|_| ErrorKind::ChannelDisconnected)?
     }
 }
 impl Handle for SupervisorHandle {
     fn latest_trusted(&self) -> Result<Option<LightBlock>, Error> {
         let (sender, receiver) = channel::bounded::<Result<Option<LightBlock>, Error>>(1);
 
-        // TODO(xla): Transform crossbeam errors into proper domain errors.
         self.sender
             .send(HandleInput::LatestTrusted(sender))
-            .unwrap();
+            .map_err(|_| ErrorKind::ChannelDisconnected)?;
+
+        receiver
+            .recv()
+            .map_err(|_| ErrorKind::ChannelDisconnected)?
+    }
 
-        // TODO(xla): Transform crossbeam errors into proper domain errors.
-        receiver.recv().unwrap()
+    fn latest_status(&self) -> Result<LatestStatus, Error> {
+        let (sender, receiver) = channel::bounded::<Result<LatestStatus, Error>>(1);
+
+        self.sender
+            .send(HandleInput::LatestStatus(sender))
+            .map_err(|_| ErrorKind::ChannelDisconnected)?;
+
+        receiver
+            .recv()
+            .map_err(|_| ErrorKind::ChannelDisconnected)?
     }
 
     fn verify_to_highest(&self) -> Result<LightBlock, Error> {
@@ -370,11 +627,15 @@ impl Handle for SupervisorHandle {
         self.verify(|sender| HandleInput::VerifyToTarget(height, sender))
     }
 
-    fn terminate(&self) {
-        let (sender, receiver) = channel::bounded::<()>(1);
+    fn terminate(&self) -> Result<(), Error> {
+        let (sender, receiver) = channel::bounded::<Result<(), Error>>(1);
 
-        self.sender.send(HandleInput::Terminate(sender)).unwrap();
+        self.sender
+            .send(HandleInput::Terminate(sender))
+            .map_err(|_| ErrorKind::ChannelDisconnected)?;
 
-        receiver.recv().unwrap()
+        receiver
+            .recv()
+            .map_err(|_| ErrorKind::ChannelDisconnected)?
     }
 }